@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use evdev::KeyCode;
+use serde::Deserialize;
+
+/// Default location of the keymap config file, overridable via `LGO1_TRIO_KEYMAP`.
+pub const DEFAULT_PATH: &str = "/etc/lgo1-trio/keymap.toml";
+
+#[derive(Debug, Deserialize)]
+struct RawFile {
+    #[serde(default)]
+    remap: HashMap<String, RawTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawTarget {
+    Key(String),
+    Chord(Vec<String>),
+}
+
+/// Maps forwarded source key codes to one or more target key codes, loaded from a TOML
+/// config file, akin to rusty-keys' `KeyMaps::from_cfg`. Use [`KeyMaps::or_identity`] to fill
+/// in pass-through entries for any forwarded key the file doesn't mention, so an incomplete
+/// `[remap]` table doesn't silently drop keys instead of forwarding them unchanged.
+#[derive(Debug, Clone)]
+pub struct KeyMaps {
+    targets: HashMap<u16, Vec<KeyCode>>,
+}
+
+impl KeyMaps {
+    /// The identity mapping: every key in `keys` maps to itself.
+    pub fn identity(keys: &[KeyCode]) -> KeyMaps {
+        KeyMaps {
+            targets: keys.iter().map(|&k| (k.0, vec![k])).collect(),
+        }
+    }
+
+    /// Loads and parses a keymap file, e.g. `/etc/lgo1-trio/keymap.toml`:
+    ///
+    /// ```toml
+    /// [remap]
+    /// KEY_VOLUMEUP = "KEY_PLAYPAUSE"
+    /// KEY_VOLUMEDOWN = ["KEY_LEFTMETA", "KEY_TAB"]
+    /// ```
+    pub fn from_cfg(path: impl AsRef<Path>) -> Result<KeyMaps, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let raw: RawFile = toml::from_str(&text)?;
+
+        let mut targets = HashMap::with_capacity(raw.remap.len());
+        for (source, target) in raw.remap {
+            let source = parse_key(&source)?;
+            let chord = match target {
+                RawTarget::Key(name) => vec![parse_key(&name)?],
+                RawTarget::Chord(names) => {
+                    names.iter().map(|n| parse_key(n)).collect::<Result<_, _>>()?
+                }
+            };
+            targets.insert(source.0, chord);
+        }
+        Ok(KeyMaps { targets })
+    }
+
+    /// The chord to emit for a given source key, or `None` if it isn't remapped.
+    pub fn chord_for(&self, source: KeyCode) -> Option<&[KeyCode]> {
+        self.targets.get(&source.0).map(Vec::as_slice)
+    }
+
+    /// Adds an identity entry for every key in `keys` not already mapped, so a config file
+    /// that only mentions some of the forwarded keys still passes the rest through unchanged
+    /// instead of dropping them.
+    pub fn or_identity(mut self, keys: &[KeyCode]) -> KeyMaps {
+        for &key in keys {
+            self.targets.entry(key.0).or_insert_with(|| vec![key]);
+        }
+        self
+    }
+
+    /// Every key code this mapping can ever emit, used to size the virtual device's
+    /// `with_keys` attribute set.
+    pub fn all_targets(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.targets.values().flatten().copied()
+    }
+}
+
+/// Recognizes the `KEY_*` names relevant to the Legion Go's forwarded buttons and common
+/// remap destinations. Extend this table as more keys need remapping.
+fn parse_key(name: &str) -> Result<KeyCode, Box<dyn std::error::Error>> {
+    Ok(match name {
+        "KEY_VOLUMEUP" => KeyCode::KEY_VOLUMEUP,
+        "KEY_VOLUMEDOWN" => KeyCode::KEY_VOLUMEDOWN,
+        "KEY_MUTE" => KeyCode::KEY_MUTE,
+        "KEY_PLAYPAUSE" => KeyCode::KEY_PLAYPAUSE,
+        "KEY_PLAYCD" => KeyCode::KEY_PLAYCD,
+        "KEY_PAUSECD" => KeyCode::KEY_PAUSECD,
+        "KEY_NEXTSONG" => KeyCode::KEY_NEXTSONG,
+        "KEY_PREVIOUSSONG" => KeyCode::KEY_PREVIOUSSONG,
+        "KEY_POWER" => KeyCode::KEY_POWER,
+        "KEY_SCREENSAVER" => KeyCode::KEY_SCREENSAVER,
+        "KEY_PROG1" => KeyCode::KEY_PROG1,
+        "KEY_PROG2" => KeyCode::KEY_PROG2,
+        "KEY_LEFTMETA" => KeyCode::KEY_LEFTMETA,
+        "KEY_RIGHTMETA" => KeyCode::KEY_RIGHTMETA,
+        "KEY_LEFTCTRL" => KeyCode::KEY_LEFTCTRL,
+        "KEY_LEFTALT" => KeyCode::KEY_LEFTALT,
+        "KEY_LEFTSHIFT" => KeyCode::KEY_LEFTSHIFT,
+        "KEY_TAB" => KeyCode::KEY_TAB,
+        "KEY_ESC" => KeyCode::KEY_ESC,
+        _ => return Err(format!("unrecognized key name: {name}").into()),
+    })
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use evdev::BusType;
+use serde::Deserialize;
+
+/// Default location of the device-identity config file, overridable via `LGO1_TRIO_DEVICES`.
+pub const DEFAULT_PATH: &str = "/etc/lgo1-trio/devices.toml";
+
+/// A hardware identity as reported by `input_id()`: bus type, vendor, and product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceId {
+    pub bus_type: u16,
+    pub vendor: u16,
+    pub product: u16,
+}
+
+/// What a recognized device means to keyboard-status detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceRole {
+    /// The Legion Go's own keys; excluded from "any external keyboard" detection.
+    Internal,
+    /// The official keyboard case; also reported via `SW_DOCK` (and optionally `SW_LID`).
+    Case,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFile {
+    #[serde(default)]
+    emit_lid: bool,
+    #[serde(default, rename = "device")]
+    devices: Vec<RawDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDevice {
+    bus: String,
+    vendor: u16,
+    product: u16,
+    role: RawRole,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawRole {
+    Internal,
+    Case,
+}
+
+/// The device-identity table: which bus/vendor/product combinations are the internal
+/// keyboard (to exclude) or the keyboard case (to report as docked), loaded from a TOML
+/// config file, mirroring how the kernel's `gpio_keys` driver maps hardware to `EV_SW` codes.
+#[derive(Debug, Clone)]
+pub struct DeviceTable {
+    roles: HashMap<DeviceId, DeviceRole>,
+    emit_lid: bool,
+}
+
+impl DeviceTable {
+    /// The identities hardcoded prior to this config file: the internal AT keyboard, the
+    /// Legion-Controller's own keyboard interface, and the official Bluetooth case.
+    pub fn built_in() -> DeviceTable {
+        DeviceTable {
+            roles: HashMap::from([
+                (
+                    DeviceId { bus_type: BusType::BUS_I8042.0, vendor: 0x1, product: 0x1 },
+                    DeviceRole::Internal,
+                ),
+                (
+                    DeviceId { bus_type: BusType::BUS_USB.0, vendor: 0x17ef, product: 0x6184 },
+                    DeviceRole::Internal,
+                ),
+                (
+                    DeviceId { bus_type: BusType::BUS_BLUETOOTH.0, vendor: 0x04e8, product: 0x7021 },
+                    DeviceRole::Case,
+                ),
+            ]),
+            emit_lid: false,
+        }
+    }
+
+    /// Loads and parses a device-identity file, e.g. `/etc/lgo1-trio/devices.toml`:
+    ///
+    /// ```toml
+    /// emit_lid = true
+    ///
+    /// [[device]]
+    /// bus = "i8042"
+    /// vendor = 0x1
+    /// product = 0x1
+    /// role = "internal"
+    ///
+    /// [[device]]
+    /// bus = "bluetooth"
+    /// vendor = 0x04e8
+    /// product = 0x7021
+    /// role = "case"
+    /// ```
+    pub fn from_cfg(path: impl AsRef<Path>) -> Result<DeviceTable, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let raw: RawFile = toml::from_str(&text)?;
+
+        let mut roles = HashMap::with_capacity(raw.devices.len());
+        for device in raw.devices {
+            let id = DeviceId {
+                bus_type: parse_bus(&device.bus)?.0,
+                vendor: device.vendor,
+                product: device.product,
+            };
+            let role = match device.role {
+                RawRole::Internal => DeviceRole::Internal,
+                RawRole::Case => DeviceRole::Case,
+            };
+            roles.insert(id, role);
+        }
+        Ok(DeviceTable { roles, emit_lid: raw.emit_lid })
+    }
+
+    /// The role of a device with the given identity, if this table has an opinion on it.
+    pub fn role_of(&self, id: DeviceId) -> Option<DeviceRole> {
+        self.roles.get(&id).copied()
+    }
+
+    /// Whether the virtual device should also derive `SW_LID` from dock state (closed, i.e.
+    /// suspend-eligible, while undocked; open while the case is attached).
+    pub fn emit_lid(&self) -> bool {
+        self.emit_lid
+    }
+}
+
+pub(crate) fn parse_bus(name: &str) -> Result<BusType, Box<dyn std::error::Error>> {
+    Ok(match name {
+        "i8042" => BusType::BUS_I8042,
+        "usb" => BusType::BUS_USB,
+        "bluetooth" => BusType::BUS_BLUETOOTH,
+        "virtual" => BusType::BUS_VIRTUAL,
+        _ => return Err(format!("unrecognized bus type: {name}").into()),
+    })
+}
@@ -0,0 +1,727 @@
+mod config;
+mod devices;
+mod reactor;
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use dbus::arg as dbus_arg;
+use dbus_crossroads::Crossroads;
+use evdev::{AttributeSet, BusType, EventType, InputEvent, KeyCode, SwitchCode, SynchronizationCode};
+
+use config::KeyMaps;
+use devices::{DeviceId, DeviceRole, DeviceTable};
+use reactor::{Epoll, EventFd, TimerFd};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KeyboardStatus {
+    /// The keyboard case is connected.
+    CaseExternal = 0x2,
+    /// Any external keyboard, excluding the keyboard case, is connected.
+    AnyExternal = 0x1,
+    /// No external keyboard is connected.
+    None = 0x0,
+}
+
+impl KeyboardStatus {
+    fn load_atomic(atomic: &AtomicU32) -> KeyboardStatus {
+        match atomic.load(Ordering::Relaxed) {
+            0x2 => KeyboardStatus::CaseExternal,
+            0x1 => KeyboardStatus::AnyExternal,
+            0x0 | _ => KeyboardStatus::None,
+        }
+    }
+
+    fn store_atomic(&self, atomic: &AtomicU32) {
+        atomic.store(*self as u32, Ordering::Relaxed)
+    }
+
+    fn is_tablet_mode(&self) -> bool {
+        *self == KeyboardStatus::None
+    }
+
+    fn is_docked(&self) -> bool {
+        *self == KeyboardStatus::CaseExternal
+    }
+
+    /// The value to report on `SW_LID`: closed (suspend-eligible) while undocked, open while
+    /// the case is attached and the user is presumably actively using the device. This is the
+    /// inverse of [`KeyboardStatus::is_docked`], not a copy of it — `SW_LID=1` means "closed"
+    /// by the `EV_SW`/logind convention, the opposite of `SW_DOCK=1` meaning "docked".
+    fn is_lid_closed(&self) -> bool {
+        !self.is_docked()
+    }
+}
+
+/// A user-requested pin on `SW_TABLET_MODE`, set via the `SetTabletModeOverride` D-Bus
+/// method, that takes precedence over the hardware-detected [`KeyboardStatus`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TabletModeOverride {
+    /// Follow the detected [`KeyboardStatus`] (the default).
+    Auto = 0,
+    /// Report tablet mode regardless of what's attached.
+    ForceOn = 1,
+    /// Never report tablet mode, even with nothing attached.
+    ForceOff = 2,
+}
+
+impl TabletModeOverride {
+    fn load_atomic(atomic: &AtomicU32) -> TabletModeOverride {
+        match atomic.load(Ordering::Relaxed) {
+            1 => TabletModeOverride::ForceOn,
+            2 => TabletModeOverride::ForceOff,
+            0 | _ => TabletModeOverride::Auto,
+        }
+    }
+
+    fn store_atomic(&self, atomic: &AtomicU32) {
+        atomic.store(*self as u32, Ordering::Relaxed)
+    }
+
+    fn from_dbus(value: u32) -> Result<TabletModeOverride> {
+        match value {
+            0 => Ok(TabletModeOverride::Auto),
+            1 => Ok(TabletModeOverride::ForceOn),
+            2 => Ok(TabletModeOverride::ForceOff),
+            other => Err(format!("unrecognized TabletModeOverride value: {other}").into()),
+        }
+    }
+}
+
+/// The `SW_TABLET_MODE` state to actually report: the override if one is set, otherwise the
+/// detected [`KeyboardStatus`].
+fn effective_tablet_mode(status: KeyboardStatus, override_mode: TabletModeOverride) -> bool {
+    match override_mode {
+        TabletModeOverride::ForceOn => true,
+        TabletModeOverride::ForceOff => false,
+        TabletModeOverride::Auto => status.is_tablet_mode(),
+    }
+}
+
+const DBUS_OBJECT_PATH: &str = "/com/youngryan/LGo1Trio";
+const DBUS_INTERFACE: &str = "com.youngryan.LGo1Trio";
+const FORWARD_KEYS: [KeyCode; 2] = [KeyCode::KEY_VOLUMEDOWN, KeyCode::KEY_VOLUMEUP];
+
+/// Recheck interval that guards against a missed or misinterpreted udev event.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(120);
+
+const TOKEN_KEYBOARD: u64 = 1;
+const TOKEN_UDEV: u64 = 2;
+const TOKEN_RECHECK_TIMER: u64 = 3;
+const TOKEN_OVERRIDE_CHANGED: u64 = 4;
+
+fn main() {
+    let atomic_status = Arc::new(AtomicU32::new(KeyboardStatus::None as u32));
+    let atomic_status2 = atomic_status.clone();
+    let atomic_status3 = atomic_status.clone();
+
+    let atomic_override = Arc::new(AtomicU32::new(TabletModeOverride::Auto as u32));
+    let atomic_override2 = atomic_override.clone();
+    let atomic_override3 = atomic_override.clone();
+
+    // Lets `SetTabletModeOverride` wake `run_reactor` immediately, instead of leaving the
+    // override applied only to the next hardware-triggered `push_keyboard_status` call.
+    let override_changed =
+        Arc::new(EventFd::new().expect("failed to create override_changed eventfd"));
+    let override_changed2 = override_changed.clone();
+
+    // (We pass references and Arc clones make the functions callable multiple
+    // times.)
+
+    let keymap_path =
+        std::env::var("LGO1_TRIO_KEYMAP").unwrap_or_else(|_| config::DEFAULT_PATH.to_owned());
+    let keymaps = Arc::new(RwLock::new(load_keymaps(&keymap_path)));
+    let keymaps2 = keymaps.clone();
+    spawn_loop("reload_keymaps_on_sighup", move || {
+        reload_keymaps_on_sighup(&keymap_path, &keymaps)
+    });
+
+    let devices_path =
+        std::env::var("LGO1_TRIO_DEVICES").unwrap_or_else(|_| devices::DEFAULT_PATH.to_owned());
+    let devices = Arc::new(load_devices(&devices_path));
+    let devices2 = devices.clone();
+    let emit_lid = devices.emit_lid();
+
+    let (virtual_s, virtual_r) = mpsc::channel::<InputEvent>();
+    spawn_loop("run_reactor", move || {
+        run_reactor(
+            &virtual_s,
+            atomic_status.clone(),
+            atomic_override.clone(),
+            devices.clone(),
+            override_changed.clone(),
+        )
+    });
+    spawn_loop("run_virtual_device", move || {
+        run_virtual_device(
+            &virtual_r,
+            atomic_status2.clone(),
+            atomic_override2.clone(),
+            keymaps2.clone(),
+            devices2.clone(),
+        )
+    });
+
+    let _ = spawn_loop("run_dbus", move || {
+        run_dbus(
+            atomic_status3.clone(),
+            atomic_override3.clone(),
+            override_changed2.clone(),
+            emit_lid,
+        )
+    })
+    .join();
+
+    unreachable!();
+}
+
+/// Spawn a new thread in an infinite loop with error reporting.
+fn spawn_loop<F, T>(name: &'static str, mut f: F) -> thread::JoinHandle<T>
+where
+    F: FnMut() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(move || {
+        loop {
+            match f() {
+                Ok(_) => {}
+                Err(err) => eprintln!("Error in {}: {}", name, err),
+            }
+            thread::sleep(Duration::from_secs(10));
+        }
+    })
+}
+
+/// Single-threaded `epoll` reactor: forwards suppressed keyboard input and recomputes
+/// [`KeyboardStatus`] on hotplug, replacing the old thread-per-source design's busy sleeps
+/// and inter-thread channels with one dispatch loop over the internal keyboard fd, the udev
+/// monitor fd, and a periodic recheck timerfd.
+fn run_reactor(
+    virtual_consumer: &mpsc::Sender<InputEvent>,
+    atomic_status: Arc<AtomicU32>,
+    atomic_override: Arc<AtomicU32>,
+    devices: Arc<DeviceTable>,
+    override_changed: Arc<EventFd>,
+) -> Result<()> {
+    let forward_codes: HashSet<u16> = FORWARD_KEYS.iter().map(|k| k.0).collect();
+    let mut internal_keyboard = evdev::enumerate()
+        .map(|(_, d)| d)
+        .find(|d| {
+            let id = d.input_id();
+            id.bus_type() == BusType::BUS_I8042 && id.vendor() == 0x1 && id.product() == 0x1
+        })
+        .ok_or("could not find internal keyboard")?;
+    // The forwarded keys we believe are currently held down, kept in sync with the real
+    // device so a SYN_DROPPED resync has a baseline to diff against.
+    let mut pressed = AttributeSet::<KeyCode>::new();
+    // Set while discarding events between a SYN_DROPPED and the SYN_REPORT that follows it.
+    let mut resyncing = false;
+    // Whether we currently hold an exclusive EVIOCGRAB on `internal_keyboard`. `Device`
+    // closes its fd on drop, which the kernel treats as releasing the grab, so an early
+    // return or a panic unwinding out of this function can never leave the device captured.
+    let mut grabbed = false;
+
+    let udev_monitor = udev::MonitorBuilder::new()?
+        .match_subsystem("input")?
+        .listen()?;
+
+    let recheck_timer = TimerFd::new()?;
+    recheck_timer.arm_periodic(RECHECK_INTERVAL)?;
+
+    let epoll = Epoll::new()?;
+    epoll.add(&internal_keyboard, TOKEN_KEYBOARD)?;
+    epoll.add(&udev_monitor, TOKEN_UDEV)?;
+    epoll.add(&recheck_timer, TOKEN_RECHECK_TIMER)?;
+    epoll.add(&*override_changed, TOKEN_OVERRIDE_CHANGED)?;
+
+    // The keyboard-capable devices udev currently reports, keyed by syspath. Kept up to date
+    // incrementally from the monitor's add/remove events instead of re-walking every
+    // `/dev/input` node on each one, which is both slow and racy during hotplug settling.
+    let mut present = enumerate_keyboards(&devices)?;
+
+    // Establish the initial status before waiting on anything.
+    push_keyboard_status(
+        virtual_consumer,
+        &atomic_status,
+        &atomic_override,
+        status_from_presence(&present),
+        &devices,
+        &mut internal_keyboard,
+        &mut grabbed,
+    )?;
+
+    loop {
+        for token in epoll.wait(3)? {
+            match token {
+                TOKEN_KEYBOARD => {
+                    // Collect the batch before processing it: fetch_events() borrows the
+                    // device mutably, and a resync needs an immutable borrow to read it back.
+                    let events: Vec<InputEvent> = internal_keyboard.fetch_events()?.collect();
+                    for event in events {
+                        if event.event_type() == EventType::SYNCHRONIZATION {
+                            match SynchronizationCode(event.code()) {
+                                SynchronizationCode::SYN_DROPPED => resyncing = true,
+                                SynchronizationCode::SYN_REPORT if resyncing => {
+                                    resyncing = false;
+                                    resync_pressed_keys(
+                                        &internal_keyboard,
+                                        &forward_codes,
+                                        &mut pressed,
+                                        virtual_consumer,
+                                    )?;
+                                }
+                                _ => {}
+                            }
+                            continue;
+                        }
+                        if resyncing {
+                            // The stream is inconsistent until the resync above runs.
+                            continue;
+                        }
+
+                        let code = event.code();
+                        if forward_codes.contains(&code) {
+                            set_key_pressed(&mut pressed, code, event.value() != 0);
+                            virtual_consumer.send(InputEvent::new(
+                                EventType::KEY.0,
+                                code,
+                                event.value(),
+                            ))?;
+                        }
+                    }
+                }
+                TOKEN_UDEV => {
+                    let mut changed = false;
+                    for event in udev_monitor.iter() {
+                        let syspath = event.syspath().to_path_buf();
+                        match event.event_type() {
+                            udev::EventType::Add => {
+                                if let Some(presence) = classify_keyboard(&event, &devices) {
+                                    present.insert(syspath, presence);
+                                    changed = true;
+                                }
+                            }
+                            udev::EventType::Remove => {
+                                changed |= present.remove(&syspath).is_some();
+                            }
+                            _ => {}
+                        }
+                    }
+                    if changed {
+                        push_keyboard_status(
+                            virtual_consumer,
+                            &atomic_status,
+                            &atomic_override,
+                            status_from_presence(&present),
+                            &devices,
+                            &mut internal_keyboard,
+                            &mut grabbed,
+                        )?;
+                    }
+                }
+                TOKEN_RECHECK_TIMER => {
+                    recheck_timer.drain()?;
+                    present = enumerate_keyboards(&devices)?;
+                    push_keyboard_status(
+                        virtual_consumer,
+                        &atomic_status,
+                        &atomic_override,
+                        status_from_presence(&present),
+                        &devices,
+                        &mut internal_keyboard,
+                        &mut grabbed,
+                    )?;
+                }
+                TOKEN_OVERRIDE_CHANGED => {
+                    // A D-Bus caller just changed `TabletModeOverride`. Re-run the full status
+                    // push against the presence we already have, rather than waiting up to
+                    // `RECHECK_INTERVAL` for the grab state to catch up with it.
+                    override_changed.drain()?;
+                    push_keyboard_status(
+                        virtual_consumer,
+                        &atomic_status,
+                        &atomic_override,
+                        status_from_presence(&present),
+                        &devices,
+                        &mut internal_keyboard,
+                        &mut grabbed,
+                    )?;
+                }
+                _ => unreachable!("unregistered epoll token"),
+            }
+        }
+    }
+}
+
+/// Publishes `SW_TABLET_MODE` (subject to any [`TabletModeOverride`]) and `SW_DOCK` (and
+/// optionally `SW_LID`) for the given [`KeyboardStatus`], grabs or releases the internal
+/// keyboard to match, and stores the status.
+fn push_keyboard_status(
+    virtual_consumer: &mpsc::Sender<InputEvent>,
+    atomic_status: &Arc<AtomicU32>,
+    atomic_override: &Arc<AtomicU32>,
+    status: KeyboardStatus,
+    devices: &DeviceTable,
+    internal_keyboard: &mut evdev::Device,
+    grabbed: &mut bool,
+) -> Result<()> {
+    let override_mode = TabletModeOverride::load_atomic(atomic_override);
+    let tablet_mode = effective_tablet_mode(status, override_mode);
+
+    // Suppress the internal volume keys from the normal input stack only while they're
+    // actually being forwarded, so an attached case or keyboard sees them normally.
+    let want_grab = tablet_mode;
+    if want_grab != *grabbed {
+        if want_grab {
+            internal_keyboard.grab()?;
+        } else {
+            internal_keyboard.ungrab()?;
+        }
+        *grabbed = want_grab;
+    }
+
+    virtual_consumer.send(InputEvent::new(
+        EventType::SWITCH.0,
+        SwitchCode::SW_TABLET_MODE.0,
+        tablet_mode as i32,
+    ))?;
+    virtual_consumer.send(InputEvent::new(
+        EventType::SWITCH.0,
+        SwitchCode::SW_DOCK.0,
+        status.is_docked() as i32,
+    ))?;
+    if devices.emit_lid() {
+        virtual_consumer.send(InputEvent::new(
+            EventType::SWITCH.0,
+            SwitchCode::SW_LID.0,
+            status.is_lid_closed() as i32,
+        ))?;
+    }
+    status.store_atomic(atomic_status);
+    Ok(())
+}
+
+/// After a `SYN_DROPPED`, diffs the device's authoritative key state against `pressed` and
+/// synthesizes the press/release events forwarding missed while the stream was inconsistent.
+fn resync_pressed_keys(
+    device: &evdev::Device,
+    forward_codes: &HashSet<u16>,
+    pressed: &mut AttributeSet<KeyCode>,
+    consumer: &mpsc::Sender<InputEvent>,
+) -> Result<()> {
+    let actual = device.get_key_state()?;
+    for &code in forward_codes {
+        let key = KeyCode(code);
+        let was_down = pressed.contains(key);
+        let is_down = actual.contains(key);
+        if was_down != is_down {
+            consumer.send(InputEvent::new(EventType::KEY.0, code, is_down as i32))?;
+            set_key_pressed(pressed, code, is_down);
+        }
+    }
+    Ok(())
+}
+
+fn set_key_pressed(pressed: &mut AttributeSet<KeyCode>, code: u16, is_down: bool) {
+    if is_down {
+        pressed.insert(KeyCode(code));
+    } else {
+        pressed.remove(KeyCode(code));
+    }
+}
+
+fn run_virtual_device(
+    event_stream: &mpsc::Receiver<InputEvent>,
+    atomic_status: Arc<AtomicU32>,
+    atomic_override: Arc<AtomicU32>,
+    keymaps: Arc<RwLock<KeyMaps>>,
+    devices: Arc<DeviceTable>,
+) -> Result<()> {
+    // The virtual device's key capabilities are fixed for its lifetime, so they're sized
+    // from the keymap in effect at startup. A SIGHUP reload can change where keys are
+    // remapped to, but not grow the set of keys the device is capable of emitting.
+    let keys = AttributeSet::<KeyCode>::from_iter(keymaps.read().unwrap().all_targets());
+    let mut switch_codes = vec![SwitchCode::SW_TABLET_MODE, SwitchCode::SW_DOCK];
+    if devices.emit_lid() {
+        switch_codes.push(SwitchCode::SW_LID);
+    }
+    let switches = AttributeSet::<SwitchCode>::from_iter(switch_codes);
+    let mut device = evdev::uinput::VirtualDevice::builder()?
+        .name("lgo1-trio virtual input device")
+        .with_keys(&keys)?
+        .with_switches(&switches)?
+        .build()?;
+
+    loop {
+        let event = event_stream.recv()?;
+        if event.event_type() != EventType::KEY {
+            device.emit(&[event])?;
+            continue;
+        }
+        let status = KeyboardStatus::load_atomic(&atomic_status);
+        let override_mode = TabletModeOverride::load_atomic(&atomic_override);
+        if !effective_tablet_mode(status, override_mode) {
+            continue;
+        }
+
+        let chord = keymaps
+            .read()
+            .unwrap()
+            .chord_for(KeyCode(event.code()))
+            .map(<[KeyCode]>::to_vec);
+        if let Some(targets) = chord {
+            let remapped: Vec<InputEvent> = targets
+                .iter()
+                .map(|k| InputEvent::new(EventType::KEY.0, k.0, event.value()))
+                .collect();
+            device.emit(&remapped)?;
+        }
+    }
+}
+
+/// Loads the keymap at `path`, filling in identity pass-through for any of [`FORWARD_KEYS`]
+/// the file doesn't remap, or falling back to an identity mapping of all of them if the file
+/// is missing or invalid.
+fn load_keymaps(path: &str) -> KeyMaps {
+    match KeyMaps::from_cfg(path) {
+        Ok(keymaps) => keymaps.or_identity(&FORWARD_KEYS),
+        Err(err) => {
+            eprintln!(
+                "could not load keymap from {}: {} (forwarding keys unmapped)",
+                path, err
+            );
+            KeyMaps::identity(&FORWARD_KEYS)
+        }
+    }
+}
+
+/// Reloads the keymap file on every `SIGHUP`, so users can rebind keys without restarting.
+fn reload_keymaps_on_sighup(path: &str, keymaps: &RwLock<KeyMaps>) -> Result<()> {
+    let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGHUP])?;
+    for _ in signals.forever() {
+        eprintln!("reloading keymap from {}", path);
+        *keymaps.write().unwrap() = load_keymaps(path);
+    }
+    Ok(())
+}
+
+/// What a keyboard-capable input device, as reported by udev, means to keyboard-status
+/// detection: the same distinction as [`DeviceRole`], plus devices the device table doesn't
+/// recognize but udev still tags as a keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyboardPresence {
+    Internal,
+    Case,
+    AnyExternal,
+}
+
+/// Reduces the current set of present keyboard-capable devices to a single [`KeyboardStatus`]:
+/// the case takes precedence over any other external keyboard, which in turn beats none at all.
+fn status_from_presence(present: &HashMap<PathBuf, KeyboardPresence>) -> KeyboardStatus {
+    if present.values().any(|&p| p == KeyboardPresence::Case) {
+        KeyboardStatus::CaseExternal
+    } else if present.values().any(|&p| p == KeyboardPresence::AnyExternal) {
+        KeyboardStatus::AnyExternal
+    } else {
+        KeyboardStatus::None
+    }
+}
+
+/// Walks every device already in udev's `input` subsystem, for an authoritative baseline at
+/// startup and for the periodic recheck. Normal hotplug updates this set incrementally
+/// instead, from the monitor's own add/remove events.
+fn enumerate_keyboards(devices: &DeviceTable) -> Result<HashMap<PathBuf, KeyboardPresence>> {
+    let mut present = HashMap::new();
+    let mut enumerator = udev::Enumerator::new()?;
+    enumerator.match_subsystem("input")?;
+    for device in enumerator.scan_devices()? {
+        if let Some(presence) = classify_keyboard(&device, devices) {
+            present.insert(device.syspath().to_path_buf(), presence);
+        }
+    }
+    Ok(present)
+}
+
+/// Classifies a udev device as a keyboard-capable input node, or `None` if it's some other
+/// kind of `input` subsystem device (a mouse, a parent `inputN` node with no `/dev` node,
+/// etc). Relies on the `ID_INPUT_KEYBOARD` property the `input_id` udev builtin already sets,
+/// rather than opening the device node to probe its key capabilities.
+fn classify_keyboard(device: &udev::Device, devices: &DeviceTable) -> Option<KeyboardPresence> {
+    let devnode = device.devnode()?;
+    if device.property_value("ID_INPUT_KEYBOARD") != Some(OsStr::new("1")) {
+        return None;
+    }
+    Some(match node_device_id(devnode).and_then(|id| devices.role_of(id)) {
+        Some(DeviceRole::Internal) => KeyboardPresence::Internal,
+        Some(DeviceRole::Case) => KeyboardPresence::Case,
+        None => KeyboardPresence::AnyExternal,
+    })
+}
+
+/// Reads the hardware identity via `EVIOCGID` (`input_id()`) by opening the device node
+/// directly, instead of udev's `ID_BUS`/`ID_VENDOR_ID`/`ID_MODEL_ID` properties: those come
+/// from udev's USB/PCI/Bluetooth rules and are never set for `i8042`/`serio` platform devices
+/// like the internal AT keyboard, which would otherwise never match [`DeviceRole::Internal`]
+/// and permanently read as an external keyboard.
+fn node_device_id(devnode: &Path) -> Option<DeviceId> {
+    let id = evdev::Device::open(devnode).ok()?.input_id();
+    Some(DeviceId { bus_type: id.bus_type().0, vendor: id.vendor(), product: id.product() })
+}
+
+/// Loads the device-identity table at `path`, falling back to the built-in identities if the
+/// file is missing or invalid.
+fn load_devices(path: &str) -> DeviceTable {
+    match DeviceTable::from_cfg(path) {
+        Ok(devices) => devices,
+        Err(err) => {
+            eprintln!(
+                "could not load device table from {}: {} (using built-in identities)",
+                path, err
+            );
+            DeviceTable::built_in()
+        }
+    }
+}
+
+/// State shared with the `com.youngryan.LGo1Trio` D-Bus interface: the detected
+/// [`KeyboardStatus`], the user's [`TabletModeOverride`], and a way to wake `run_reactor` to
+/// re-evaluate grab state and re-publish switches the moment the override changes, rather than
+/// waiting on the next hardware-triggered [`push_keyboard_status`] call.
+struct DbusContext {
+    atomic_status: Arc<AtomicU32>,
+    atomic_override: Arc<AtomicU32>,
+    override_changed: Arc<EventFd>,
+}
+
+fn run_dbus(
+    atomic_status: Arc<AtomicU32>,
+    atomic_override: Arc<AtomicU32>,
+    override_changed: Arc<EventFd>,
+    emit_lid: bool,
+) -> Result<()> {
+    use dbus::channel::{MatchingReceiver, Sender};
+
+    let ctx = DbusContext {
+        atomic_status: atomic_status.clone(),
+        atomic_override: atomic_override.clone(),
+        override_changed,
+    };
+    let mut cr = make_crossroads(ctx, emit_lid);
+    let conn = dbus::blocking::LocalConnection::new_system()?;
+    conn.request_name("com.youngryan.LGo1Trio", false, true, false)?;
+    conn.start_receive(
+        dbus::message::MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            cr.handle_message(msg, conn).unwrap();
+            true
+        }),
+    );
+
+    let mut last_seen: Option<(KeyboardStatus, TabletModeOverride)> = Option::None;
+    loop {
+        conn.process(Duration::from_millis(100))?;
+
+        let status = KeyboardStatus::load_atomic(&atomic_status);
+        let override_mode = TabletModeOverride::load_atomic(&atomic_override);
+        if last_seen.is_none_or(|(s, o)| s != status || o != override_mode) {
+            let mut changed_props = dbus_arg::PropMap::new();
+            changed_props.insert(
+                "KeyboardStatus".to_owned(),
+                dbus_arg::Variant(Box::new(status as u32)),
+            );
+            changed_props.insert(
+                "TabletMode".to_owned(),
+                dbus_arg::Variant(Box::new(effective_tablet_mode(status, override_mode))),
+            );
+            changed_props.insert(
+                "TabletModeOverride".to_owned(),
+                dbus_arg::Variant(Box::new(override_mode as u32)),
+            );
+            changed_props.insert(
+                "Dock".to_owned(),
+                dbus_arg::Variant(Box::new(status.is_docked())),
+            );
+            if emit_lid {
+                changed_props.insert(
+                    "Lid".to_owned(),
+                    dbus_arg::Variant(Box::new(status.is_lid_closed())),
+                );
+            }
+            conn.send(
+                dbus::Message::signal(
+                    &DBUS_OBJECT_PATH.into(),
+                    &"org.freedesktop.DBus.Properties".into(),
+                    &"PropertiesChanged".into(),
+                )
+                .append3(
+                    DBUS_INTERFACE,
+                    changed_props,
+                    dbus_arg::Array::new(std::iter::empty::<&str>()),
+                ),
+            )
+            .map_err(|_| "failed to send properties changed message")?;
+
+            last_seen = Option::Some((status, override_mode));
+        }
+    }
+}
+
+fn make_crossroads(ctx: DbusContext, emit_lid: bool) -> Crossroads {
+    let mut cr = Crossroads::new();
+    let iface_token = cr.register(
+        DBUS_INTERFACE,
+        |b: &mut dbus_crossroads::IfaceBuilder<DbusContext>| {
+            b.property("KeyboardStatus").get(|_, obj| {
+                let status = KeyboardStatus::load_atomic(&obj.atomic_status);
+                Ok(status as u32)
+            });
+            b.property("TabletMode").get(|_, obj| {
+                let status = KeyboardStatus::load_atomic(&obj.atomic_status);
+                let override_mode = TabletModeOverride::load_atomic(&obj.atomic_override);
+                Ok(effective_tablet_mode(status, override_mode))
+            });
+            b.property("TabletModeOverride").get(|_, obj| {
+                let override_mode = TabletModeOverride::load_atomic(&obj.atomic_override);
+                Ok(override_mode as u32)
+            });
+            b.property("Dock").get(|_, obj| {
+                let dock = KeyboardStatus::load_atomic(&obj.atomic_status).is_docked();
+                Ok(dock)
+            });
+            if emit_lid {
+                b.property("Lid").get(|_, obj| {
+                    let lid = KeyboardStatus::load_atomic(&obj.atomic_status).is_lid_closed();
+                    Ok(lid)
+                });
+            }
+            b.method(
+                "SetTabletModeOverride",
+                ("value",),
+                (),
+                |_, obj, (value,): (u32,)| {
+                    let override_mode = TabletModeOverride::from_dbus(value)
+                        .map_err(|err| dbus::MethodErr::invalid_arg(&err.to_string()))?;
+                    override_mode.store_atomic(&obj.atomic_override);
+
+                    // Don't wait for the next hardware-triggered push: the user is pinning the
+                    // switch state right now, so wake run_reactor to re-run
+                    // push_keyboard_status immediately, which is the only place that keeps the
+                    // internal keyboard's grab state consistent with the override.
+                    obj.override_changed
+                        .signal()
+                        .map_err(|_| dbus::MethodErr::failed("reactor is gone"))?;
+                    Ok(())
+                },
+            );
+        },
+    );
+    cr.insert(DBUS_OBJECT_PATH, &[iface_token], ctx);
+    cr
+}
@@ -0,0 +1,192 @@
+//! Thin wrappers around `epoll` and `timerfd`, just enough to dispatch a handful of event
+//! sources from a single thread instead of spawning one thread per source.
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+
+/// An `epoll` instance that dispatches registered file descriptors by an opaque `u64` token.
+pub struct Epoll {
+    fd: RawFd,
+}
+
+impl Epoll {
+    pub fn new() -> io::Result<Epoll> {
+        let fd = unsafe { libc::epoll_create1(0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Epoll { fd })
+    }
+
+    /// Registers `source` for readability, tagging it with `token` for [`Epoll::wait`].
+    pub fn add(&self, source: &impl AsRawFd, token: u64) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: token,
+        };
+        let result = unsafe {
+            libc::epoll_ctl(self.fd, libc::EPOLL_CTL_ADD, source.as_raw_fd(), &mut event)
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Blocks until at least one registered source is readable, returning the tokens passed
+    /// to [`Epoll::add`] for each one (a source may appear more than once).
+    pub fn wait(&self, max_events: usize) -> io::Result<Vec<u64>> {
+        let mut events = vec![libc::epoll_event { events: 0, u64: 0 }; max_events];
+        let n = unsafe {
+            libc::epoll_wait(
+                self.fd,
+                events.as_mut_ptr(),
+                events.len() as libc::c_int,
+                -1,
+            )
+        };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(events[..n as usize].iter().map(|e| e.u64).collect())
+    }
+}
+
+impl Drop for Epoll {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+impl AsRawFd for Epoll {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+/// A `timerfd` used for the periodic recheck.
+pub struct TimerFd {
+    fd: RawFd,
+}
+
+impl TimerFd {
+    pub fn new() -> io::Result<TimerFd> {
+        let fd = unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(TimerFd { fd })
+    }
+
+    /// Arms the timer to fire every `interval`, starting after one `interval`.
+    pub fn arm_periodic(&self, interval: Duration) -> io::Result<()> {
+        self.settime(libc::itimerspec {
+            it_interval: duration_to_timespec(interval),
+            it_value: duration_to_timespec(interval),
+        })
+    }
+
+    fn settime(&self, spec: libc::itimerspec) -> io::Result<()> {
+        let result = unsafe { libc::timerfd_settime(self.fd, 0, &spec, std::ptr::null_mut()) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Consumes the expiration counter so the fd stops reporting readable.
+    pub fn drain(&self) -> io::Result<u64> {
+        let mut count: u64 = 0;
+        let result = unsafe {
+            libc::read(
+                self.fd,
+                &mut count as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for TimerFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+impl AsRawFd for TimerFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+/// An `eventfd` used to wake the reactor from another thread, e.g. to re-evaluate keyboard
+/// status the moment a D-Bus caller changes some piece of state the reactor owns.
+pub struct EventFd {
+    fd: RawFd,
+}
+
+impl EventFd {
+    pub fn new() -> io::Result<EventFd> {
+        let fd = unsafe { libc::eventfd(0, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventFd { fd })
+    }
+
+    /// Wakes up anyone blocked in [`Epoll::wait`] on this fd.
+    pub fn signal(&self) -> io::Result<()> {
+        let value: u64 = 1;
+        let result = unsafe {
+            libc::write(
+                self.fd,
+                &value as *const u64 as *const libc::c_void,
+                std::mem::size_of::<u64>(),
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Consumes the counter so the fd stops reporting readable.
+    pub fn drain(&self) -> io::Result<u64> {
+        let mut count: u64 = 0;
+        let result = unsafe {
+            libc::read(
+                self.fd,
+                &mut count as *mut u64 as *mut libc::c_void,
+                std::mem::size_of::<u64>(),
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(count)
+    }
+}
+
+impl Drop for EventFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+fn duration_to_timespec(d: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: d.as_secs() as libc::time_t,
+        tv_nsec: d.subsec_nanos() as libc::c_long,
+    }
+}